@@ -0,0 +1,578 @@
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+
+use crate::irep::{Irept, StringInterner};
+
+pub type BlockId = usize;
+
+/// A straight-line run of instructions with no internal control transfers.
+pub struct BasicBlock {
+    /// Index of the first instruction (inclusive) in the function body.
+    pub start: usize,
+    /// One past the index of the last instruction in the function body.
+    pub end: usize,
+    pub successors: Vec<BlockId>,
+    pub predecessors: Vec<BlockId>,
+}
+
+/// Control-flow graph over the instructions of a GOTO function body.
+///
+/// `body.subt` is taken to be the ordered list of instructions. An
+/// instruction is a control transfer when its id is `goto`: it carries a
+/// `target` named sub (an integer-literal irep naming the destination
+/// instruction index) and a `guard` named sub. A `true` guard means the
+/// jump is unconditional, so no fall-through edge is added; any other guard
+/// is conditional and falls through to the next instruction as well.
+pub struct Cfg {
+    pub body: Rc<Irept>,
+    pub blocks: Vec<BasicBlock>,
+    pub entry: BlockId,
+}
+
+impl Cfg {
+    pub fn build(body: Rc<Irept>, interner: &mut StringInterner) -> Self {
+        let goto_id = interner.get_or_intern("goto");
+        let target_key = interner.get_or_intern("target");
+        let guard_key = interner.get_or_intern("guard");
+        let true_id = interner.get_or_intern("true");
+
+        let n = body.subt.len();
+
+        let mut targets: Vec<Option<usize>> = vec![None; n];
+        for (i, instr) in body.subt.iter().enumerate() {
+            if instr.id != goto_id {
+                continue;
+            }
+            if let Some(target_irep) = instr.named_subt.get(&target_key) {
+                if let Some(text) = interner.resolve(target_irep.id) {
+                    if let Ok(idx) = text.parse::<usize>() {
+                        // Targets outside the body (truncated/rewritten IR,
+                        // hand-built fixtures, ...) can't be resolved to a
+                        // block; treat the goto as having no known target
+                        // rather than indexing out of bounds later.
+                        if idx < n {
+                            targets[i] = Some(idx);
+                        }
+                    }
+                }
+            }
+        }
+
+        let is_unconditional = |i: usize| -> bool {
+            body.subt[i]
+                .named_subt
+                .get(&guard_key)
+                .map(|g| g.id == true_id)
+                .unwrap_or(false)
+        };
+
+        if n == 0 {
+            return Cfg {
+                body,
+                blocks: vec![BasicBlock {
+                    start: 0,
+                    end: 0,
+                    successors: Vec::new(),
+                    predecessors: Vec::new(),
+                }],
+                entry: 0,
+            };
+        }
+
+        let mut leaders: Vec<usize> = vec![0];
+        for (i, target) in targets.iter().enumerate() {
+            if let Some(target) = target {
+                leaders.push(*target);
+                if i + 1 < n {
+                    leaders.push(i + 1);
+                }
+            }
+        }
+        leaders.sort_unstable();
+        leaders.dedup();
+
+        let mut block_of = vec![0usize; n];
+        for (block_id, &start) in leaders.iter().enumerate() {
+            let end = leaders.get(block_id + 1).copied().unwrap_or(n);
+            for slot in block_of.iter_mut().take(end).skip(start) {
+                *slot = block_id;
+            }
+        }
+
+        let mut blocks: Vec<BasicBlock> = leaders
+            .iter()
+            .enumerate()
+            .map(|(block_id, &start)| BasicBlock {
+                start,
+                end: leaders.get(block_id + 1).copied().unwrap_or(n),
+                successors: Vec::new(),
+                predecessors: Vec::new(),
+            })
+            .collect();
+
+        let mut successors: Vec<Vec<BlockId>> = vec![Vec::new(); blocks.len()];
+        for (block_id, block) in blocks.iter().enumerate() {
+            if block.start == block.end {
+                continue;
+            }
+            let last = block.end - 1;
+            let has_fallthrough_block = block_id + 1 < blocks.len();
+            match targets[last] {
+                Some(target) => {
+                    successors[block_id].push(block_of[target]);
+                    if !is_unconditional(last) && has_fallthrough_block {
+                        successors[block_id].push(block_id + 1);
+                    }
+                }
+                None if has_fallthrough_block => {
+                    successors[block_id].push(block_id + 1);
+                }
+                None => {}
+            }
+        }
+
+        for (block_id, succs) in successors.into_iter().enumerate() {
+            for &succ in &succs {
+                blocks[succ].predecessors.push(block_id);
+            }
+            blocks[block_id].successors = succs;
+        }
+
+        Cfg {
+            body,
+            blocks,
+            entry: 0,
+        }
+    }
+}
+
+/// Immediate-dominator tree over a [`Cfg`], computed with the
+/// Cooper-Harvey-Kennedy iterative algorithm.
+pub struct Dominators {
+    idom: Vec<BlockId>,
+}
+
+impl Dominators {
+    pub fn compute(cfg: &Cfg) -> Self {
+        let n = cfg.blocks.len();
+
+        let mut visited = vec![false; n];
+        let mut postorder = Vec::with_capacity(n);
+        visit_postorder(cfg, cfg.entry, &mut visited, &mut postorder);
+
+        // `pon[b]` is b's position in postorder; the entry has the largest
+        // number since it is the last node finished.
+        let mut pon = vec![0usize; n];
+        for (i, &block) in postorder.iter().enumerate() {
+            pon[block] = i;
+        }
+        let rpo: Vec<BlockId> = postorder.into_iter().rev().collect();
+
+        let mut idom: Vec<Option<BlockId>> = vec![None; n];
+        idom[cfg.entry] = Some(cfg.entry);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &b in &rpo {
+                if b == cfg.entry {
+                    continue;
+                }
+                let mut new_idom = None;
+                for &p in &cfg.blocks[b].predecessors {
+                    if idom[p].is_none() {
+                        continue; // predecessor not processed yet this sweep
+                    }
+                    new_idom = Some(match new_idom {
+                        None => p,
+                        Some(cur) => intersect(cur, p, &idom, &pon),
+                    });
+                }
+                if new_idom.is_some() && idom[b] != new_idom {
+                    idom[b] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        let idom = idom.into_iter().map(|d| d.unwrap_or(cfg.entry)).collect();
+        Dominators { idom }
+    }
+
+    pub fn idom(&self, block: BlockId) -> BlockId {
+        self.idom[block]
+    }
+
+    /// Whether `a` dominates `b` (reflexive: every block dominates itself).
+    pub fn dominates(&self, a: BlockId, b: BlockId) -> bool {
+        let mut node = b;
+        loop {
+            if node == a {
+                return true;
+            }
+            let parent = self.idom[node];
+            if parent == node {
+                return false; // reached the entry without finding `a`
+            }
+            node = parent;
+        }
+    }
+}
+
+fn visit_postorder(cfg: &Cfg, node: BlockId, visited: &mut [bool], order: &mut Vec<BlockId>) {
+    visited[node] = true;
+    for &succ in &cfg.blocks[node].successors {
+        if !visited[succ] {
+            visit_postorder(cfg, succ, visited, order);
+        }
+    }
+    order.push(node);
+}
+
+fn intersect(mut f1: BlockId, mut f2: BlockId, idom: &[Option<BlockId>], pon: &[usize]) -> BlockId {
+    while f1 != f2 {
+        while pon[f1] < pon[f2] {
+            f1 = idom[f1].expect("predecessor already processed");
+        }
+        while pon[f2] < pon[f1] {
+            f2 = idom[f2].expect("predecessor already processed");
+        }
+    }
+    f1
+}
+
+/// A natural loop: the set of blocks that can reach its back-edge source
+/// without passing through the header, plus the header itself.
+pub struct NaturalLoop {
+    header: BlockId,
+    body: BTreeSet<BlockId>,
+}
+
+impl NaturalLoop {
+    pub fn header(&self) -> BlockId {
+        self.header
+    }
+
+    pub fn body_blocks(&self) -> impl Iterator<Item = BlockId> + '_ {
+        self.body.iter().copied()
+    }
+
+    pub fn contains(&self, block: BlockId) -> bool {
+        self.body.contains(&block)
+    }
+}
+
+/// Nesting of a CFG's natural loops, keyed by header block.
+pub struct LoopTree {
+    loops: Vec<NaturalLoop>,
+    parent: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+}
+
+impl LoopTree {
+    /// Finds all back edges (`u -> v` where `v` dominates `u`), merges loops
+    /// that share a header, and nests each loop inside the smallest loop
+    /// whose body contains its header.
+    pub fn build(cfg: &Cfg, dom: &Dominators) -> Self {
+        let mut by_header: HashMap<BlockId, BTreeSet<BlockId>> = HashMap::new();
+        for (u, block) in cfg.blocks.iter().enumerate() {
+            for &v in &block.successors {
+                if dom.dominates(v, u) {
+                    let body = natural_loop_body(cfg, u, v);
+                    by_header.entry(v).or_default().extend(body);
+                }
+            }
+        }
+
+        let mut loops: Vec<NaturalLoop> = by_header
+            .into_iter()
+            .map(|(header, body)| NaturalLoop { header, body })
+            .collect();
+        loops.sort_by_key(|l| l.header);
+
+        let n = loops.len();
+        let mut parent = vec![None; n];
+        for i in 0..n {
+            let mut best: Option<usize> = None;
+            for j in 0..n {
+                if i == j || !loops[j].body.contains(&loops[i].header) {
+                    continue;
+                }
+                if best.is_none_or(|b| loops[j].body.len() < loops[b].body.len()) {
+                    best = Some(j);
+                }
+            }
+            parent[i] = best;
+        }
+
+        let mut children = vec![Vec::new(); n];
+        for (i, p) in parent.iter().enumerate() {
+            if let Some(p) = p {
+                children[*p].push(i);
+            }
+        }
+
+        LoopTree {
+            loops,
+            parent,
+            children,
+        }
+    }
+
+    pub fn loops(&self) -> &[NaturalLoop] {
+        &self.loops
+    }
+
+    pub fn parent(&self, idx: usize) -> Option<usize> {
+        self.parent[idx]
+    }
+
+    pub fn children(&self, idx: usize) -> &[usize] {
+        &self.children[idx]
+    }
+}
+
+/// Renders a `Cfg` as a Graphviz DOT digraph: one node per basic block
+/// listing its instructions' resolved ids, with edges for each control-flow
+/// successor, so the block structure can be debugged visually alongside
+/// `Irept::to_dot`.
+pub fn cfg_to_dot(cfg: &Cfg, interner: &StringInterner) -> String {
+    let mut out = String::new();
+    out.push_str("digraph Cfg {\n  node [shape=box];\n");
+
+    for (id, block) in cfg.blocks.iter().enumerate() {
+        let mut label = format!("bb{}\\l", id);
+        for instr in &cfg.body.subt[block.start..block.end] {
+            let text = interner.resolve(instr.id).unwrap_or("<NOT FOUND>");
+            label.push_str(&escape_dot_label(text));
+            label.push_str("\\l");
+        }
+        out.push_str(&format!("  b{} [label=\"{}\"];\n", id, label));
+    }
+
+    for (id, block) in cfg.blocks.iter().enumerate() {
+        for &succ in &block.successors {
+            out.push_str(&format!("  b{} -> b{};\n", id, succ));
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn escape_dot_label(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn natural_loop_body(cfg: &Cfg, u: BlockId, v: BlockId) -> BTreeSet<BlockId> {
+    let mut body = BTreeSet::new();
+    body.insert(v);
+    body.insert(u);
+
+    let mut worklist = vec![u];
+    while let Some(node) = worklist.pop() {
+        if node == v {
+            continue;
+        }
+        for &pred in &cfg.blocks[node].predecessors {
+            if body.insert(pred) {
+                worklist.push(pred);
+            }
+        }
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irep::StringInterner;
+
+    fn int_literal(value: usize, interner: &mut StringInterner) -> Irept {
+        Irept::new(&value.to_string(), interner)
+    }
+
+    fn goto(target: usize, guard_true: bool, interner: &mut StringInterner) -> Irept {
+        let mut instr = Irept::new("goto", interner);
+        let target_key = interner.get_or_intern("target");
+        instr
+            .named_subt
+            .insert(target_key, Rc::new(int_literal(target, interner)));
+
+        let guard_key = interner.get_or_intern("guard");
+        let guard = if guard_true {
+            Irept::new("true", interner)
+        } else {
+            Irept::new("cond", interner)
+        };
+        instr.named_subt.insert(guard_key, Rc::new(guard));
+        instr
+    }
+
+    fn plain(interner: &mut StringInterner) -> Irept {
+        Irept::new("assign", interner)
+    }
+
+    fn function_body(instrs: Vec<Irept>, interner: &mut StringInterner) -> Rc<Irept> {
+        let mut body = Irept::new("code_block", interner);
+        body.subt = instrs.into_iter().map(Rc::new).collect();
+        Rc::new(body)
+    }
+
+    #[test]
+    fn test_linear_body_is_single_block() {
+        let mut interner = StringInterner::new();
+        let body = function_body(
+            vec![plain(&mut interner), plain(&mut interner), plain(&mut interner)],
+            &mut interner,
+        );
+        let cfg = Cfg::build(body, &mut interner);
+
+        assert_eq!(cfg.blocks.len(), 1);
+        assert!(cfg.blocks[0].successors.is_empty());
+    }
+
+    #[test]
+    fn test_unconditional_goto_has_single_successor() {
+        let mut interner = StringInterner::new();
+        let body = function_body(
+            vec![
+                goto(2, true, &mut interner),
+                plain(&mut interner),
+                plain(&mut interner),
+            ],
+            &mut interner,
+        );
+        let cfg = Cfg::build(body, &mut interner);
+
+        // block 0 = [goto], block 1 = [plain @1], block 2 = [plain @2]
+        assert_eq!(cfg.blocks.len(), 3);
+        assert_eq!(cfg.blocks[0].successors, vec![2]);
+    }
+
+    #[test]
+    fn test_conditional_goto_has_fallthrough_and_target() {
+        let mut interner = StringInterner::new();
+        let body = function_body(
+            vec![
+                goto(2, false, &mut interner),
+                plain(&mut interner),
+                plain(&mut interner),
+            ],
+            &mut interner,
+        );
+        let cfg = Cfg::build(body, &mut interner);
+
+        let mut succs = cfg.blocks[0].successors.clone();
+        succs.sort_unstable();
+        assert_eq!(succs, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_diamond_dominators() {
+        let mut interner = StringInterner::new();
+        // block 0 = [0], block 1 = [1, 2], block 2 = [3], block 3 = [4]:
+        // 0 branches to 2 or falls to 1, 1 jumps past 2, both reach 3.
+        let body = function_body(
+            vec![
+                goto(3, false, &mut interner), // 0: branch to the else arm or fall to 1
+                plain(&mut interner),          // 1: then arm
+                goto(4, true, &mut interner),  // 2: unconditional goto to merge
+                plain(&mut interner),          // 3: else arm, falls to merge
+                plain(&mut interner),          // 4: merge
+            ],
+            &mut interner,
+        );
+        let cfg = Cfg::build(body, &mut interner);
+        let dom = Dominators::compute(&cfg);
+
+        // entry dominates everything
+        for block in 0..cfg.blocks.len() {
+            assert!(dom.dominates(cfg.entry, block));
+        }
+        // the merge block is not dominated by either branch arm alone
+        let merge_block = cfg.blocks.len() - 1;
+        assert_eq!(dom.idom(merge_block), cfg.entry);
+    }
+
+    #[test]
+    fn test_block_dominates_itself() {
+        let mut interner = StringInterner::new();
+        let body = function_body(vec![plain(&mut interner)], &mut interner);
+        let cfg = Cfg::build(body, &mut interner);
+        let dom = Dominators::compute(&cfg);
+
+        assert!(dom.dominates(0, 0));
+    }
+
+    #[test]
+    fn test_natural_loop_body_and_header() {
+        let mut interner = StringInterner::new();
+        // 0: plain                         -- pre-header
+        // 1: plain                         -- loop header
+        // 2: if (cond) goto 4              -- loop exit test
+        // 3: goto 1 (unconditional)        -- back edge
+        // 4: plain                         -- after the loop
+        let body = function_body(
+            vec![
+                plain(&mut interner),
+                plain(&mut interner),
+                goto(4, false, &mut interner),
+                goto(1, true, &mut interner),
+                plain(&mut interner),
+            ],
+            &mut interner,
+        );
+        let cfg = Cfg::build(body, &mut interner);
+        let dom = Dominators::compute(&cfg);
+        let tree = LoopTree::build(&cfg, &dom);
+
+        assert_eq!(tree.loops().len(), 1);
+        let natural_loop = &tree.loops()[0];
+        assert_eq!(natural_loop.header(), 1);
+
+        let mut blocks: Vec<BlockId> = natural_loop.body_blocks().collect();
+        blocks.sort_unstable();
+        assert_eq!(blocks, vec![1, 2]);
+        assert!(natural_loop.contains(1));
+        assert!(natural_loop.contains(2));
+        assert!(!natural_loop.contains(0));
+
+        assert_eq!(tree.parent(0), None);
+        assert!(tree.children(0).is_empty());
+    }
+
+    #[test]
+    fn test_no_loops_in_linear_body() {
+        let mut interner = StringInterner::new();
+        let body = function_body(
+            vec![plain(&mut interner), plain(&mut interner)],
+            &mut interner,
+        );
+        let cfg = Cfg::build(body, &mut interner);
+        let dom = Dominators::compute(&cfg);
+        let tree = LoopTree::build(&cfg, &dom);
+
+        assert!(tree.loops().is_empty());
+    }
+
+    #[test]
+    fn test_cfg_to_dot_renders_blocks_and_edges() {
+        let mut interner = StringInterner::new();
+        let body = function_body(
+            vec![
+                goto(2, true, &mut interner),
+                plain(&mut interner),
+                plain(&mut interner),
+            ],
+            &mut interner,
+        );
+        let cfg = Cfg::build(body, &mut interner);
+
+        let dot = cfg_to_dot(&cfg, &interner);
+        assert!(dot.starts_with("digraph Cfg {\n"));
+        assert!(dot.contains("bb0"));
+        assert!(dot.contains("goto"));
+        assert!(dot.contains("b0 -> b2;"));
+    }
+}
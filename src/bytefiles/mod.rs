@@ -1,83 +1,162 @@
 use crate::irep::Irept;
 use crate::irep::StringInterner;
-use std::collections::HashMap;
-use std::fs;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
 use std::rc::Rc;
-use std::str;
 
-pub struct ByteReader {
-    file: Vec<u8>,
-    pointer: usize,
+pub mod writer;
+pub use writer::{write_gbf, ByteWriter};
+
+/// An error produced while reading the GBF binary irep format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadError {
+    /// The stream ended before the value being read was complete.
+    UnexpectedEof,
+    /// The 3-byte magic at the start of the stream wasn't `GBF`.
+    BadHeader([u8; 3]),
+    /// The format version word isn't one this reader understands.
+    UnsupportedVersion(u32),
+    /// A `\`-escape in a string was cut short by the end of the stream.
+    MalformedEscape,
+    /// An irep's subt/named_subt/comments weren't closed by the 0 terminator.
+    UnterminatedIrep,
+    /// A reference id was read while its own definition was still being
+    /// parsed, i.e. it refers to itself (directly or through a cycle)
+    /// instead of to an already-completed irep.
+    DanglingReference(u32),
+    /// The underlying file couldn't be opened for reading.
+    Io(String),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::UnexpectedEof => write!(f, "unexpected end of GBF stream"),
+            ReadError::BadHeader(got) => write!(
+                f,
+                "invalid ESBMC header, found: {}{}{}",
+                got[0] as char, got[1] as char, got[2] as char
+            ),
+            ReadError::UnsupportedVersion(version) => {
+                write!(f, "invalid ESBMC version, found {}", version)
+            }
+            ReadError::MalformedEscape => {
+                write!(f, "malformed escape sequence in ESBMC string")
+            }
+            ReadError::UnterminatedIrep => write!(f, "irep not terminated"),
+            ReadError::DanglingReference(id) => {
+                write!(f, "dangling reference to irep id {}", id)
+            }
+            ReadError::Io(message) => write!(f, "could not read file: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+/// Reads the GBF binary irep format from any buffered byte source.
+///
+/// Operating over `std::io::Read` (rather than a fully-materialized
+/// `Vec<u8>`) lets multi-gigabyte GBF dumps be streamed instead of loaded
+/// into RAM in one go. A single-byte lookahead buffer preserves the
+/// `peek()`/`get()` API the parsing loops below rely on.
+pub struct ByteReader<R: Read> {
+    source: R,
+    lookahead: Option<u8>,
     pub irep_container: HashMap<u32, Rc<Irept>>,
+    pending_irep_ids: HashSet<u32>,
     string_ref_container: HashMap<u32, usize>,
     pub string_interner: StringInterner,
 }
 
-impl From<Vec<u8>> for ByteReader {
+impl From<Vec<u8>> for ByteReader<Cursor<Vec<u8>>> {
     fn from(data: Vec<u8>) -> Self {
+        ByteReader::new(Cursor::new(data))
+    }
+}
+
+impl ByteReader<BufReader<File>> {
+    pub fn read_file(path: &str) -> Result<Self, ReadError> {
+        let file = File::open(path).map_err(|err| ReadError::Io(err.to_string()))?;
+        Ok(ByteReader::new(BufReader::new(file)))
+    }
+}
+
+impl<R: Read> ByteReader<R> {
+    pub fn new(source: R) -> Self {
         ByteReader {
-            file: data,
-            pointer: 0,
+            source,
+            lookahead: None,
             irep_container: HashMap::new(),
+            pending_irep_ids: HashSet::new(),
             string_ref_container: HashMap::new(),
             string_interner: StringInterner::new(),
         }
     }
-}
 
-impl ByteReader {
-    pub fn read_file(path: &str) -> Self {
-        let byte_content = fs::read(path).expect(format!("Could not read file {}", path).as_str());
-        ByteReader::from(byte_content)
+    fn fill(&mut self) -> Result<(), ReadError> {
+        if self.lookahead.is_none() {
+            let mut buf = [0u8; 1];
+            match self.source.read(&mut buf) {
+                Ok(0) | Err(_) => return Err(ReadError::UnexpectedEof),
+                Ok(_) => self.lookahead = Some(buf[0]),
+            }
+        }
+        Ok(())
     }
 
-    fn peek(&self) -> u8 {
-        self.file[self.pointer]
+    fn peek(&mut self) -> Result<u8, ReadError> {
+        self.fill()?;
+        Ok(self.lookahead.unwrap())
     }
 
-    fn get(&mut self) -> u8 {
-        let value = self.file[self.pointer];
-        self.pointer += 1;
-        value
+    fn get(&mut self) -> Result<u8, ReadError> {
+        self.fill()?;
+        Ok(self.lookahead.take().unwrap())
     }
 
     // Reference parsing. First try the cache, if not available then parse the irep
-    pub fn read_esbmc_reference(&mut self) -> Rc<Irept> {
-        let id = self.read_esbmc_word();
-        if self.irep_container.contains_key(&id) {
-            return self.irep_container.get(&id).unwrap().clone();
+    pub fn read_esbmc_reference(&mut self) -> Result<Rc<Irept>, ReadError> {
+        let id = self.read_esbmc_word()?;
+        if let Some(cached) = self.irep_container.get(&id) {
+            return Ok(cached.clone());
+        }
+        if !self.pending_irep_ids.insert(id) {
+            return Err(ReadError::DanglingReference(id));
         }
 
-        let irep_id = self.read_esbmc_string_ref();
+        let irep_id = self.read_esbmc_string_ref()?;
         // Sub-expression
         let mut irep_sub: Vec<Rc<Irept>> = Vec::new();
-        while self.peek() == b'S' {
-            self.pointer += 1;
-            let sub = self.read_esbmc_reference();
+        while self.peek()? == b'S' {
+            self.get()?;
+            let sub = self.read_esbmc_reference()?;
             irep_sub.push(sub);
         }
 
         // Named sub
         let mut named_sub: HashMap<usize, Rc<Irept>> = HashMap::new();
-        while self.peek() == b'N' {
-            self.pointer += 1;
-            let named_id = self.read_esbmc_string_ref();
+        while self.peek()? == b'N' {
+            self.get()?;
+            let named_id = self.read_esbmc_string_ref()?;
             // TODO: assert named_id[0] != '#'
-            named_sub.insert(named_id, self.read_esbmc_reference());
+            named_sub.insert(named_id, self.read_esbmc_reference()?);
         }
 
         // Comment?
         let mut comments_sub: HashMap<usize, Rc<Irept>> = HashMap::new();
-        while self.peek() == b'C' {
-            self.pointer += 1;
-            let named_id = self.read_esbmc_string_ref();
+        while self.peek()? == b'C' {
+            self.get()?;
+            let named_id = self.read_esbmc_string_ref()?;
             // TODO: assert named_id[0] == '#'
-            comments_sub.insert(named_id, self.read_esbmc_reference());
+            comments_sub.insert(named_id, self.read_esbmc_reference()?);
         }
 
-        let end_value = self.get();
+        let end_value = self.get()?;
         if end_value != 0 {
-            panic!("Irep not terminated.");
+            return Err(ReadError::UnterminatedIrep);
         }
 
         let result = Irept {
@@ -87,70 +166,69 @@ impl ByteReader {
             comments: comments_sub,
         };
 
-        self.irep_container.insert(id, Rc::new(result)).unwrap()
+        self.pending_irep_ids.remove(&id);
+        let rc = Rc::new(result);
+        self.irep_container.insert(id, rc.clone());
+        Ok(rc)
     }
 
     // String parsing.
-    pub fn read_esbmc_string(&mut self) -> String {
+    pub fn read_esbmc_string(&mut self) -> Result<String, ReadError> {
         let mut bytes = Vec::<u8>::new();
-        while self.peek() != 0 {
-            let c = self.get();
+        while self.peek()? != 0 {
+            let c = self.get()?;
             if c == b'\\' {
-                bytes.push(self.get());
+                match self.get() {
+                    Ok(escaped) => bytes.push(escaped),
+                    Err(_) => return Err(ReadError::MalformedEscape),
+                }
             } else {
                 bytes.push(c);
             }
         }
-        self.pointer += 1;
-        let value = String::from_utf8_lossy(&bytes).to_string();
-        value
+        self.get()?; // consume the terminating NUL
+        Ok(String::from_utf8_lossy(&bytes).to_string())
     }
 
     // String reference parsing. Similar than the irep one
-    pub fn read_esbmc_string_ref(&mut self) -> usize {
-        let id = self.read_esbmc_word();
+    pub fn read_esbmc_string_ref(&mut self) -> Result<usize, ReadError> {
+        let id = self.read_esbmc_word()?;
 
-        if self.string_ref_container.contains_key(&id) {
-            return self.string_ref_container.get(&id).unwrap().clone();
+        if let Some(&interner_id) = self.string_ref_container.get(&id) {
+            return Ok(interner_id);
         }
 
-        let value = self.read_esbmc_string();
+        let value = self.read_esbmc_string()?;
         let interner_id = self.string_interner.get_or_intern(&value);
         self.string_ref_container.insert(id, interner_id);
-        interner_id
+        Ok(interner_id)
     }
 
     // Word reading (as u32)
-    pub fn read_esbmc_word(&mut self) -> u32 {
-        let raw_bytes: &[u8; 4] = self.file[self.pointer..self.pointer + 4]
-            .try_into()
-            .expect("Slice should be of length 4");
-        self.pointer += 4;
+    pub fn read_esbmc_word(&mut self) -> Result<u32, ReadError> {
+        let mut raw_bytes = [0u8; 4];
+        for byte in raw_bytes.iter_mut() {
+            *byte = self.get()?;
+        }
 
         // ESBMC generates this in BE form
-        u32::from_be_bytes(*raw_bytes)
+        Ok(u32::from_be_bytes(raw_bytes))
     }
 
-    pub fn check_esbmc_header(&mut self) -> Result<(), String> {
-        let header: &[u8; 3] = self.file[0..3]
-            .try_into()
-            .expect("GBF does not contain header");
+    pub fn check_esbmc_header(&mut self) -> Result<(), ReadError> {
+        let header = [self.get()?, self.get()?, self.get()?];
 
         let gbf: [u8; 3] = [b'G', b'B', b'F'];
-        if *header != gbf {
-            return Err(format!(
-                "Invalid ESBMC header. Found: {}{}{}",
-                header[0], header[1], header[2]
-            ));
+        if header != gbf {
+            return Err(ReadError::BadHeader(header));
         }
-        self.pointer = 3;
         Ok(())
     }
 
-    pub fn check_esbmc_version(&mut self) -> Result<(), String> {
-        let version = self.read_esbmc_word();
+    pub fn check_esbmc_version(&mut self) -> Result<(), ReadError> {
+        let version = self.read_esbmc_word()?;
         if version != 1 {
-            return Err(format!("Invalid ESBMC version. Found {}", version));
+            return Err(ReadError::UnsupportedVersion(version));
         }
         Ok(())
     }
@@ -168,11 +246,20 @@ mod tests {
         let data = vec![1, 2, 3, 4, 5];
         let mut reader = ByteReader::from(data);
 
-        assert_eq!(reader.peek(), 1);
-        assert_eq!(reader.peek(), 1); // peek doesn't advance
-        assert_eq!(reader.get(), 1); // get advances
-        assert_eq!(reader.peek(), 2);
-        assert_eq!(reader.get(), 2);
+        assert_eq!(reader.peek().unwrap(), 1);
+        assert_eq!(reader.peek().unwrap(), 1); // peek doesn't advance
+        assert_eq!(reader.get().unwrap(), 1); // get advances
+        assert_eq!(reader.peek().unwrap(), 2);
+        assert_eq!(reader.get().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_get_past_end_of_stream_is_unexpected_eof() {
+        let data = vec![1];
+        let mut reader = ByteReader::from(data);
+
+        assert_eq!(reader.get().unwrap(), 1);
+        assert_eq!(reader.get().unwrap_err(), ReadError::UnexpectedEof);
     }
 
     // ===== Header validation =====
@@ -180,11 +267,12 @@ mod tests {
     #[test]
     fn test_check_esbmc_header_valid() {
         let mut data = vec![b'G', b'B', b'F'];
-        data.extend_from_slice(&[0u8; 4]); // Padding for next read
+        data.extend_from_slice(&[0, 0, 0, 7]); // Padding for next read
         let mut reader = ByteReader::from(data);
 
         assert!(reader.check_esbmc_header().is_ok());
-        assert_eq!(reader.pointer, 3);
+        // the stream should now be positioned right after the header
+        assert_eq!(reader.read_esbmc_word().unwrap(), 7);
     }
 
     #[test]
@@ -193,8 +281,7 @@ mod tests {
         let mut reader = ByteReader::from(data);
 
         let result = reader.check_esbmc_header();
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid ESBMC header"));
+        assert_eq!(result, Err(ReadError::BadHeader([b'X', b'B', b'F'])));
     }
 
     #[test]
@@ -213,6 +300,14 @@ mod tests {
         assert!(reader.check_esbmc_header().is_err());
     }
 
+    #[test]
+    fn test_check_esbmc_header_unexpected_eof() {
+        let data = vec![b'G', b'B'];
+        let mut reader = ByteReader::from(data);
+
+        assert_eq!(reader.check_esbmc_header(), Err(ReadError::UnexpectedEof));
+    }
+
     // ===== Version validation =====
 
     #[test]
@@ -230,9 +325,7 @@ mod tests {
         let data = vec![0, 0, 0, 2];
         let mut reader = ByteReader::from(data);
 
-        let result = reader.check_esbmc_version();
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid ESBMC version"));
+        assert_eq!(reader.check_esbmc_version(), Err(ReadError::UnsupportedVersion(2)));
     }
 
     #[test]
@@ -251,9 +344,8 @@ mod tests {
         let data = vec![0x12, 0x34, 0x56, 0x78];
         let mut reader = ByteReader::from(data);
 
-        let word = reader.read_esbmc_word();
+        let word = reader.read_esbmc_word().unwrap();
         assert_eq!(word, 0x12345678);
-        assert_eq!(reader.pointer, 4);
     }
 
     #[test]
@@ -261,7 +353,7 @@ mod tests {
         let data = vec![0, 0, 0, 0];
         let mut reader = ByteReader::from(data);
 
-        assert_eq!(reader.read_esbmc_word(), 0);
+        assert_eq!(reader.read_esbmc_word().unwrap(), 0);
     }
 
     #[test]
@@ -270,7 +362,7 @@ mod tests {
         let data = vec![0xFF, 0xFF, 0xFF, 0xFF];
         let mut reader = ByteReader::from(data);
 
-        assert_eq!(reader.read_esbmc_word(), 0xFFFFFFFF);
+        assert_eq!(reader.read_esbmc_word().unwrap(), 0xFFFFFFFF);
     }
 
     #[test]
@@ -282,19 +374,27 @@ mod tests {
         ];
         let mut reader = ByteReader::from(data);
 
-        assert_eq!(reader.read_esbmc_word(), 1);
-        assert_eq!(reader.read_esbmc_word(), 2);
-        assert_eq!(reader.read_esbmc_word(), 3);
+        assert_eq!(reader.read_esbmc_word().unwrap(), 1);
+        assert_eq!(reader.read_esbmc_word().unwrap(), 2);
+        assert_eq!(reader.read_esbmc_word().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_read_esbmc_word_unexpected_eof() {
+        let data = vec![0, 0];
+        let mut reader = ByteReader::from(data);
+
+        assert_eq!(reader.read_esbmc_word(), Err(ReadError::UnexpectedEof));
     }
 
     // ===== String reading =====
 
     #[test]
     fn test_read_esbmc_string_simple() {
-        let mut data = b"hello\0".to_vec();
+        let data = b"hello\0".to_vec();
         let mut reader = ByteReader::from(data);
 
-        let s = reader.read_esbmc_string();
+        let s = reader.read_esbmc_string().unwrap();
         assert_eq!(s, "hello");
     }
 
@@ -303,16 +403,35 @@ mod tests {
         let data = vec![0];
         let mut reader = ByteReader::from(data);
 
-        let s = reader.read_esbmc_string();
+        let s = reader.read_esbmc_string().unwrap();
         assert_eq!(s, "");
     }
 
     #[test]
     fn test_read_esbmc_string_with_numbers() {
-        let mut data = b"test123\0".to_vec();
+        let data = b"test123\0".to_vec();
+        let mut reader = ByteReader::from(data);
+
+        assert_eq!(reader.read_esbmc_string().unwrap(), "test123");
+    }
+
+    #[test]
+    fn test_read_esbmc_string_escaped_backslash() {
+        let data = b"a\\\\b\0".to_vec();
+        let mut reader = ByteReader::from(data);
+
+        assert_eq!(reader.read_esbmc_string().unwrap(), "a\\b");
+    }
+
+    #[test]
+    fn test_read_esbmc_string_truncated_escape_is_malformed() {
+        let data = vec![b'a', b'\\'];
         let mut reader = ByteReader::from(data);
 
-        assert_eq!(reader.read_esbmc_string(), "test123");
+        assert_eq!(
+            reader.read_esbmc_string().unwrap_err(),
+            ReadError::MalformedEscape
+        );
     }
 
     // ===== Initialization =====
@@ -320,19 +439,18 @@ mod tests {
     #[test]
     fn test_from_vec_u8() {
         let data = vec![1, 2, 3, 4];
-        let reader = ByteReader::from(data);
+        let mut reader = ByteReader::from(data);
 
-        assert_eq!(reader.pointer, 0);
+        // position starts at the beginning of the stream
+        assert_eq!(reader.peek().unwrap(), 1);
         assert_eq!(reader.irep_container.len(), 0);
         assert_eq!(reader.string_ref_container.len(), 0);
     }
 
     #[test]
     fn test_read_file_not_found() {
-        let result = std::panic::catch_unwind(|| {
-            ByteReader::read_file("nonexistent/file.esbmc");
-        });
-        assert!(result.is_err());
+        let result = ByteReader::read_file("nonexistent/file.esbmc");
+        assert!(matches!(result, Err(ReadError::Io(_))));
     }
 
     // ===== Container operations =====
@@ -367,4 +485,58 @@ mod tests {
         assert_eq!(id1, id2); // Same string should have same id
         assert_ne!(id1, id3); // Different strings should have different ids
     }
+
+    // ===== Round trip =====
+
+    #[test]
+    fn test_read_esbmc_reference_unterminated_is_an_error() {
+        // A reference with no subt/named/comments tags and a non-zero
+        // terminator byte instead of the expected 0.
+        let mut interner = StringInterner::new();
+        let irep_id = interner.get_or_intern("leaf");
+        let mut data = vec![0, 0, 0, 0]; // reference id 0
+        data.extend_from_slice(&(irep_id as u32).to_be_bytes()); // string ref id
+        data.push(b'x'); // string bytes: the irep id is read as "x"
+        data.push(0); // NUL terminator for the string
+        data.push(1); // irep terminator: should be 0, this is malformed
+
+        let mut reader = ByteReader::from(data);
+        let result = reader.read_esbmc_reference();
+        assert_eq!(result.unwrap_err(), ReadError::UnterminatedIrep);
+    }
+
+    #[test]
+    fn test_write_then_read_round_trip() {
+        let mut interner = StringInterner::new();
+        let leaf = Rc::new(Irept::new("leaf", &mut interner));
+
+        let mut root = Irept::new("root", &mut interner);
+        root.subt.push(leaf.clone());
+        root.subt.push(leaf);
+        let named_child = Rc::new(Irept::new("named", &mut interner));
+        root.named_subt
+            .insert(interner.get_or_intern("field"), named_child);
+        let root = Rc::new(root);
+
+        let bytes = write_gbf(&root, &interner);
+
+        let mut reader = ByteReader::from(bytes);
+        reader.check_esbmc_header().unwrap();
+        reader.check_esbmc_version().unwrap();
+        let read_back = reader.read_esbmc_reference().unwrap();
+
+        assert_eq!(
+            reader.string_interner.resolve(read_back.id),
+            Some("root")
+        );
+        assert_eq!(read_back.subt.len(), 2);
+        // The two subt entries were the same Rc before writing, so they
+        // should come back as the same interned irep after reading.
+        assert!(Rc::ptr_eq(&read_back.subt[0], &read_back.subt[1]));
+        assert_eq!(
+            reader.string_interner.resolve(read_back.subt[0].id),
+            Some("leaf")
+        );
+        assert_eq!(read_back.named_subt.len(), 1);
+    }
 }
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::irep::{Irept, StringInterner};
+
+/// Symmetric counterpart to `ByteReader`: serializes an `Irept`/
+/// `StringInterner` pair back into a byte-identical GBF stream. Shared
+/// subtrees are assigned a stable reference id the first time they are
+/// encountered (mirroring `ByteReader::irep_container`) and are emitted as a
+/// bare reference word on every later occurrence, so round-tripping a
+/// hash-consed DAG doesn't blow it back up into a tree.
+pub struct ByteWriter<'a> {
+    bytes: Vec<u8>,
+    irep_ids: HashMap<*const Irept, u32>,
+    string_ref_ids: HashMap<usize, u32>,
+    next_irep_id: u32,
+    next_string_ref_id: u32,
+    interner: &'a StringInterner,
+}
+
+impl<'a> ByteWriter<'a> {
+    pub fn new(interner: &'a StringInterner) -> Self {
+        Self {
+            bytes: Vec::new(),
+            irep_ids: HashMap::new(),
+            string_ref_ids: HashMap::new(),
+            next_irep_id: 0,
+            next_string_ref_id: 0,
+            interner,
+        }
+    }
+
+    pub fn write_header(&mut self) {
+        self.bytes.extend_from_slice(b"GBF");
+    }
+
+    pub fn write_version(&mut self) {
+        self.write_esbmc_word(1);
+    }
+
+    pub fn write_esbmc_word(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    // String writing. Inverse of `ByteReader::read_esbmc_string`: a literal
+    // `\` or NUL byte is escaped with a leading `\`, everything else is
+    // copied verbatim, terminated by an unescaped NUL.
+    pub fn write_esbmc_string(&mut self, value: &str) {
+        for byte in value.bytes() {
+            if byte == b'\\' || byte == 0 {
+                self.bytes.push(b'\\');
+            }
+            self.bytes.push(byte);
+        }
+        self.bytes.push(0);
+    }
+
+    // String reference writing. Caches by interner id so a string already
+    // seen is emitted as just its reference word, mirroring
+    // `ByteReader::read_esbmc_string_ref`.
+    pub fn write_esbmc_string_ref(&mut self, interner_id: usize) {
+        if let Some(&id) = self.string_ref_ids.get(&interner_id) {
+            self.write_esbmc_word(id);
+            return;
+        }
+
+        let id = self.next_string_ref_id;
+        self.next_string_ref_id += 1;
+        self.string_ref_ids.insert(interner_id, id);
+        self.write_esbmc_word(id);
+
+        let value = self.interner.resolve(interner_id).unwrap_or("");
+        self.write_esbmc_string(value);
+    }
+
+    // Reference writing. Caches by the `Rc`'s address so a subtree already
+    // emitted (e.g. via an `IrepPool`) is written just once and every other
+    // parent points at it by reference word, mirroring
+    // `ByteReader::read_esbmc_reference`.
+    pub fn write_esbmc_reference(&mut self, irep: &Rc<Irept>) {
+        let ptr = Rc::as_ptr(irep);
+        if let Some(&id) = self.irep_ids.get(&ptr) {
+            self.write_esbmc_word(id);
+            return;
+        }
+
+        let id = self.next_irep_id;
+        self.next_irep_id += 1;
+        self.irep_ids.insert(ptr, id);
+        self.write_esbmc_word(id);
+
+        self.write_esbmc_string_ref(irep.id);
+
+        for sub in &irep.subt {
+            self.bytes.push(b'S');
+            self.write_esbmc_reference(sub);
+        }
+
+        let mut named: Vec<_> = irep.named_subt.iter().collect();
+        named.sort_by_key(|(key, _)| **key);
+        for (key, child) in named {
+            self.bytes.push(b'N');
+            self.write_esbmc_string_ref(*key);
+            self.write_esbmc_reference(child);
+        }
+
+        let mut comments: Vec<_> = irep.comments.iter().collect();
+        comments.sort_by_key(|(key, _)| **key);
+        for (key, child) in comments {
+            self.bytes.push(b'C');
+            self.write_esbmc_string_ref(*key);
+            self.write_esbmc_reference(child);
+        }
+
+        self.bytes.push(0);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Convenience wrapper that writes a full GBF stream (header, version,
+/// root reference) for `root`.
+pub fn write_gbf(root: &Rc<Irept>, interner: &StringInterner) -> Vec<u8> {
+    let mut writer = ByteWriter::new(interner);
+    writer.write_header();
+    writer.write_version();
+    writer.write_esbmc_reference(root);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_header_and_version() {
+        let interner = StringInterner::new();
+        let mut writer = ByteWriter::new(&interner);
+        writer.write_header();
+        writer.write_version();
+
+        let bytes = writer.finish();
+        assert_eq!(&bytes[0..3], b"GBF");
+        assert_eq!(&bytes[3..7], &[0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_write_esbmc_string_escapes_backslash_and_nul() {
+        let interner = StringInterner::new();
+        let mut writer = ByteWriter::new(&interner);
+        writer.write_esbmc_string("a\\b\0c");
+
+        let bytes = writer.finish();
+        assert_eq!(bytes, vec![b'a', b'\\', b'\\', b'b', b'\\', 0, b'c', 0]);
+    }
+
+    #[test]
+    fn test_write_esbmc_string_ref_caches_repeats() {
+        let mut interner = StringInterner::new();
+        let id = interner.get_or_intern("hello");
+        let mut writer = ByteWriter::new(&interner);
+
+        writer.write_esbmc_string_ref(id);
+        let after_first = writer.bytes.len();
+        writer.write_esbmc_string_ref(id);
+        let after_second = writer.bytes.len();
+
+        // The second occurrence is only a 4-byte reference word.
+        assert_eq!(after_second - after_first, 4);
+    }
+
+    #[test]
+    fn test_write_gbf_shares_repeated_reference() {
+        let mut interner = StringInterner::new();
+        let leaf = Rc::new(Irept::new("leaf", &mut interner));
+
+        let mut root = Irept::new("root", &mut interner);
+        root.subt.push(leaf.clone());
+        root.subt.push(leaf);
+        let root = Rc::new(root);
+
+        let bytes = write_gbf(&root, &interner);
+
+        // header(3) + version(4) + root ref word(4) + root string ref(4) +
+        // "root\0"(5) + 'S' + leaf ref word(4) + leaf string ref(4) +
+        // "leaf\0"(5) + terminator(1) + 'S' + leaf ref word(4, cached) +
+        // terminator(1)
+        let expected_len = 3 + 4 + 4 + 4 + 5 + 1 + 4 + 4 + 5 + 1 + 1 + 4 + 1;
+        assert_eq!(bytes.len(), expected_len);
+    }
+}
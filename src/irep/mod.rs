@@ -1,6 +1,11 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
+pub mod parser;
+pub mod pool;
+pub use parser::{parse_irept, print_irept, ParseError};
+pub use pool::{IrepBuilder, IrepPool, IrepPoolStats};
+
 pub struct StringInterner {
     map: HashMap<Box<str>, usize>,
     strings: Vec<Box<str>>,
@@ -135,6 +140,80 @@ impl PartialEq for Irept {
 }
 impl Eq for Irept {}
 
+impl Irept {
+    /// Renders this node (and everything reachable from it) as a Graphviz
+    /// DOT digraph: one node per `Irept` labeled with its resolved id, solid
+    /// edges to `subt` children, labeled edges to `named_subt` entries (the
+    /// edge label is the resolved key), and dashed labeled edges to
+    /// `comments`. Nodes shared via hash-consing (e.g. an `IrepPool`) are
+    /// emitted once and referenced by every parent, so the DAG structure is
+    /// visible instead of being flattened back into a tree.
+    pub fn to_dot(&self, interner: &StringInterner) -> String {
+        let mut out = String::new();
+        out.push_str("digraph Irept {\n");
+        let mut ids: HashMap<*const Irept, usize> = HashMap::new();
+        let mut next_id = 0usize;
+        write_irept_dot(self, interner, &mut ids, &mut next_id, &mut out);
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn write_irept_dot(
+    node: &Irept,
+    interner: &StringInterner,
+    ids: &mut HashMap<*const Irept, usize>,
+    next_id: &mut usize,
+    out: &mut String,
+) -> usize {
+    let ptr = node as *const Irept;
+    if let Some(&id) = ids.get(&ptr) {
+        return id;
+    }
+    let id = *next_id;
+    *next_id += 1;
+    ids.insert(ptr, id);
+
+    out.push_str(&format!(
+        "  n{} [label=\"{}\"];\n",
+        id,
+        escape_dot_label(resolve_or_missing(interner, node.id))
+    ));
+
+    for child in &node.subt {
+        let child_id = write_irept_dot(child, interner, ids, next_id, out);
+        out.push_str(&format!("  n{} -> n{};\n", id, child_id));
+    }
+    for (key, child) in &node.named_subt {
+        let child_id = write_irept_dot(child, interner, ids, next_id, out);
+        out.push_str(&format!(
+            "  n{} -> n{} [label=\"{}\"];\n",
+            id,
+            child_id,
+            escape_dot_label(resolve_or_missing(interner, *key))
+        ));
+    }
+    for (key, child) in &node.comments {
+        let child_id = write_irept_dot(child, interner, ids, next_id, out);
+        out.push_str(&format!(
+            "  n{} -> n{} [label=\"{}\", style=dashed];\n",
+            id,
+            child_id,
+            escape_dot_label(resolve_or_missing(interner, *key))
+        ));
+    }
+
+    id
+}
+
+fn resolve_or_missing(interner: &StringInterner, id: usize) -> &str {
+    interner.resolve(id).unwrap_or("<NOT FOUND>")
+}
+
+fn escape_dot_label(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -662,4 +741,71 @@ mod tests {
         assert_eq!(interner.resolve(index1), Some("hello"));
         assert_eq!(interner.resolve(index2), Some("world"));
     }
+
+    // ============================================================================
+    // DOT export tests
+    // ============================================================================
+
+    #[test]
+    fn test_to_dot_contains_node_label() {
+        let mut interner = StringInterner::new();
+        let irept = Irept::new("leaf", &mut interner);
+
+        let dot = irept.to_dot(&interner);
+        assert!(dot.starts_with("digraph Irept {\n"));
+        assert!(dot.contains("label=\"leaf\""));
+    }
+
+    #[test]
+    fn test_to_dot_renders_subt_edge() {
+        let mut interner = StringInterner::new();
+        let mut parent = Irept::new("parent", &mut interner);
+        let child = Irept::new("child", &mut interner);
+        parent.subt.push(Rc::new(child));
+
+        let dot = parent.to_dot(&interner);
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("label=\"child\""));
+    }
+
+    #[test]
+    fn test_to_dot_renders_named_subt_edge_label() {
+        let mut interner = StringInterner::new();
+        let mut parent = Irept::new("parent", &mut interner);
+        let child = Irept::new("child", &mut interner);
+        let field = interner.get_or_intern("field");
+        parent.named_subt.insert(field, Rc::new(child));
+
+        let dot = parent.to_dot(&interner);
+        assert!(dot.contains("n0 -> n1 [label=\"field\"];"));
+    }
+
+    #[test]
+    fn test_to_dot_renders_comment_as_dashed() {
+        let mut interner = StringInterner::new();
+        let mut irept = Irept::new("node", &mut interner);
+        let comment = Irept::new("note", &mut interner);
+        let key = interner.get_or_intern("comment");
+        irept.comments.insert(key, Rc::new(comment));
+
+        let dot = irept.to_dot(&interner);
+        assert!(dot.contains("style=dashed"));
+    }
+
+    #[test]
+    fn test_to_dot_emits_shared_node_once() {
+        let mut interner = StringInterner::new();
+        let shared = Rc::new(Irept::new("shared", &mut interner));
+
+        let mut parent1 = Irept::new("parent1", &mut interner);
+        parent1.subt.push(shared.clone());
+        let mut root = Irept::new("root", &mut interner);
+        root.subt.push(Rc::new(parent1));
+        root.subt.push(shared);
+
+        let dot = root.to_dot(&interner);
+        // "shared" should only be declared as a node once, even though two
+        // parents point at it.
+        assert_eq!(dot.matches("label=\"shared\"").count(), 1);
+    }
 }
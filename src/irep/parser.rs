@@ -0,0 +1,666 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use super::{Irept, StringInterner};
+
+/// An error produced while parsing the textual `Irept` format, with the
+/// byte span in the source text that triggered it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: (usize, usize),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at {}..{})", self.message, self.span.0, self.span.1)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Op(String),
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Colon,
+    Hash,
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Assoc {
+    Left,
+    Right,
+}
+
+/// Binary operators recognized by the precedence-climbing expression parser,
+/// ordered loosest-to-tightest. `^` (exponentiation) is right-associative so
+/// that `a ^ b ^ c` folds as `a ^ (b ^ c)`; every other operator is
+/// left-associative.
+const OPERATORS: &[(&str, u8, Assoc)] = &[
+    ("||", 0, Assoc::Left),
+    ("&&", 1, Assoc::Left),
+    ("==", 2, Assoc::Left),
+    ("!=", 2, Assoc::Left),
+    ("<", 3, Assoc::Left),
+    ("<=", 3, Assoc::Left),
+    (">", 3, Assoc::Left),
+    (">=", 3, Assoc::Left),
+    ("+", 4, Assoc::Left),
+    ("-", 4, Assoc::Left),
+    ("*", 5, Assoc::Left),
+    ("/", 5, Assoc::Left),
+    ("%", 5, Assoc::Left),
+    ("^", 6, Assoc::Right),
+];
+
+fn lookup_operator(op: &str) -> Option<(u8, Assoc)> {
+    OPERATORS
+        .iter()
+        .find(|(s, _, _)| *s == op)
+        .map(|(_, prec, assoc)| (*prec, *assoc))
+}
+
+fn is_op_char(c: char) -> bool {
+    matches!(c, '&' | '|' | '=' | '!' | '<' | '>' | '+' | '-' | '*' | '/' | '%' | '^')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(i, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        match c {
+            '(' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::LParen, start: i, end: i + 1 });
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::RParen, start: i, end: i + 1 });
+            }
+            '{' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::LBrace, start: i, end: i + 1 });
+            }
+            '}' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::RBrace, start: i, end: i + 1 });
+            }
+            ':' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::Colon, start: i, end: i + 1 });
+            }
+            '#' => {
+                chars.next();
+                tokens.push(Token { kind: TokenKind::Hash, start: i, end: i + 1 });
+            }
+            '"' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => s.push(escaped),
+                            None => {
+                                return Err(ParseError {
+                                    message: "unterminated escape in string literal".to_string(),
+                                    span: (i, input.len()),
+                                })
+                            }
+                        },
+                        Some((_, other)) => s.push(other),
+                        None => {
+                            return Err(ParseError {
+                                message: "unterminated string literal".to_string(),
+                                span: (i, input.len()),
+                            })
+                        }
+                    }
+                }
+                let end = chars.peek().map(|&(j, _)| j).unwrap_or(input.len());
+                tokens.push(Token { kind: TokenKind::Str(s), start: i, end });
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut s = String::new();
+                while let Some(&(_, c2)) = chars.peek() {
+                    if c2.is_alphanumeric() || c2 == '_' {
+                        s.push(c2);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let end = i + s.len();
+                tokens.push(Token { kind: TokenKind::Ident(s), start: i, end });
+            }
+            c if c.is_ascii_digit() => {
+                let mut s = String::new();
+                let mut seen_dot = false;
+                while let Some(&(_, c2)) = chars.peek() {
+                    if c2.is_ascii_digit() {
+                        s.push(c2);
+                        chars.next();
+                    } else if c2 == '.' && !seen_dot {
+                        seen_dot = true;
+                        s.push(c2);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let end = i + s.len();
+                tokens.push(Token { kind: TokenKind::Number(s), start: i, end });
+            }
+            c if is_op_char(c) => {
+                let mut candidate = String::new();
+                candidate.push(c);
+                chars.next();
+                if let Some(&(_, c2)) = chars.peek() {
+                    let mut two = candidate.clone();
+                    two.push(c2);
+                    if lookup_operator(&two).is_some() {
+                        candidate = two;
+                        chars.next();
+                    }
+                }
+                if lookup_operator(&candidate).is_none() {
+                    return Err(ParseError {
+                        message: format!("unknown operator `{}`", candidate),
+                        span: (i, i + candidate.len()),
+                    });
+                }
+                let end = i + candidate.len();
+                tokens.push(Token { kind: TokenKind::Op(candidate), start: i, end });
+            }
+            other => {
+                return Err(ParseError {
+                    message: format!("unexpected character `{}`", other),
+                    span: (i, i + other.len_utf8()),
+                });
+            }
+        }
+    }
+
+    tokens.push(Token { kind: TokenKind::Eof, start: input.len(), end: input.len() });
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    interner: &'a mut StringInterner,
+}
+
+impl<'a> Parser<'a> {
+    fn peek_kind(&self) -> TokenKind {
+        self.tokens[self.pos].kind.clone()
+    }
+
+    fn peek_span(&self) -> (usize, usize) {
+        let tok = &self.tokens[self.pos];
+        (tok.start, tok.end)
+    }
+
+    fn advance(&mut self) -> Token {
+        let tok = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn expect(&mut self, expected: TokenKind) -> Result<Token, ParseError> {
+        let tok = self.advance();
+        if tok.kind == expected {
+            Ok(tok)
+        } else {
+            Err(ParseError {
+                message: format!("expected {:?}, found {:?}", expected, tok.kind),
+                span: (tok.start, tok.end),
+            })
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, ParseError> {
+        let tok = self.advance();
+        match tok.kind {
+            TokenKind::Ident(s) => Ok(s),
+            other => Err(ParseError {
+                message: format!("expected an identifier, found {:?}", other),
+                span: (tok.start, tok.end),
+            }),
+        }
+    }
+
+    fn expect_leaf_token(&mut self) -> Result<String, ParseError> {
+        let tok = self.advance();
+        match tok.kind {
+            TokenKind::Ident(s) | TokenKind::Number(s) | TokenKind::Str(s) => Ok(s),
+            other => Err(ParseError {
+                message: format!("expected an irep id, found {:?}", other),
+                span: (tok.start, tok.end),
+            }),
+        }
+    }
+
+    fn peek_op(&self) -> Option<String> {
+        match &self.tokens[self.pos].kind {
+            TokenKind::Op(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    /// Precedence-climbing expression parser: parses a primary, then loops
+    /// while the next operator's precedence is >= `min_prec`, recursing into
+    /// the right-hand side with `prec + 1` for left-associative operators
+    /// (or `prec` for right-associative ones) so same-precedence operators
+    /// fold with the correct associativity.
+    fn parse_expr(&mut self, min_prec: u8) -> Result<Irept, ParseError> {
+        let mut lhs = self.parse_primary()?;
+
+        while let Some(op) = self.peek_op() {
+            let (prec, assoc) = lookup_operator(&op).expect("tokenizer only emits known operators");
+            if prec < min_prec {
+                break;
+            }
+            self.advance();
+
+            let next_min = match assoc {
+                Assoc::Left => prec + 1,
+                Assoc::Right => prec,
+            };
+            let rhs = self.parse_expr(next_min)?;
+
+            let id = self.interner.get_or_intern(&op);
+            lhs = Irept {
+                id,
+                subt: vec![Rc::new(lhs), Rc::new(rhs)],
+                named_subt: HashMap::new(),
+                comments: HashMap::new(),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_primary(&mut self) -> Result<Irept, ParseError> {
+        let tok = self.advance();
+        match tok.kind {
+            TokenKind::LParen => {
+                let node = self.parse_sexpr_body()?;
+                self.expect(TokenKind::RParen)?;
+                Ok(node)
+            }
+            TokenKind::LBrace => {
+                let node = self.parse_expr(0)?;
+                self.expect(TokenKind::RBrace)?;
+                Ok(node)
+            }
+            TokenKind::Ident(s) => Ok(Irept::new(&s, self.interner)),
+            TokenKind::Number(s) => Ok(Irept::new(&s, self.interner)),
+            TokenKind::Str(s) => Ok(Irept::new(&s, self.interner)),
+            other => Err(ParseError {
+                message: format!("expected an expression, found {:?}", other),
+                span: (tok.start, tok.end),
+            }),
+        }
+    }
+
+    /// Parses the body of a `(id sub... :key val... #comment val...)`
+    /// s-expression, having already consumed the opening `(`.
+    fn parse_sexpr_body(&mut self) -> Result<Irept, ParseError> {
+        let head = self.expect_leaf_token()?;
+        let mut node = Irept::new(&head, self.interner);
+
+        loop {
+            match self.peek_kind() {
+                TokenKind::RParen => break,
+                TokenKind::Eof => {
+                    return Err(ParseError {
+                        message: "unterminated s-expression".to_string(),
+                        span: self.peek_span(),
+                    })
+                }
+                TokenKind::Colon => {
+                    self.advance();
+                    let key = self.expect_ident()?;
+                    let key_id = self.interner.get_or_intern(&key);
+                    let value = self.parse_expr(0)?;
+                    node.named_subt.insert(key_id, Rc::new(value));
+                }
+                TokenKind::Hash => {
+                    self.advance();
+                    let key = self.expect_ident()?;
+                    let key_id = self.interner.get_or_intern(&key);
+                    let value = self.parse_expr(0)?;
+                    node.comments.insert(key_id, Rc::new(value));
+                }
+                _ => {
+                    let value = self.parse_expr(0)?;
+                    node.subt.push(Rc::new(value));
+                }
+            }
+        }
+
+        Ok(node)
+    }
+}
+
+/// Parses the compact textual `Irept` format: plain identifiers/numbers are
+/// leaves, `(id sub... :key val... #comment val...)` builds structural
+/// nodes, `{ expr }` groups a sub-expression, and arithmetic/boolean infix
+/// operators (`+ - * / % ^ < <= > >= == != && ||`) fold via precedence
+/// climbing into nested binary operator nodes. Interns every id and key into
+/// `interner`.
+pub fn parse_irept(input: &str, interner: &mut StringInterner) -> Result<Irept, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0, interner };
+
+    let node = parser.parse_expr(0)?;
+    match parser.peek_kind() {
+        TokenKind::Eof => Ok(node),
+        other => Err(ParseError {
+            message: format!("unexpected trailing input: {:?}", other),
+            span: parser.peek_span(),
+        }),
+    }
+}
+
+fn is_bare_token(s: &str) -> bool {
+    let mut chars = s.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return false,
+    };
+    if first.is_alphabetic() || first == '_' {
+        s.chars().all(|c| c.is_alphanumeric() || c == '_')
+    } else if first.is_ascii_digit() {
+        let mut seen_dot = false;
+        s.chars().all(|c| {
+            if c.is_ascii_digit() {
+                true
+            } else if c == '.' && !seen_dot {
+                seen_dot = true;
+                true
+            } else {
+                false
+            }
+        })
+    } else {
+        false
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        if c == '\\' || c == '"' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+fn resolve_token(id: usize, interner: &StringInterner) -> String {
+    let s = interner.resolve(id).unwrap_or("<NOT FOUND>");
+    if is_bare_token(s) {
+        s.to_string()
+    } else {
+        quote(s)
+    }
+}
+
+fn is_operator_node(node: &Irept, interner: &StringInterner) -> bool {
+    node.subt.len() == 2
+        && node.named_subt.is_empty()
+        && node.comments.is_empty()
+        && interner
+            .resolve(node.id)
+            .map(|op| lookup_operator(op).is_some())
+            .unwrap_or(false)
+}
+
+/// Pretty-prints an `Irept` back into the textual format parsed by
+/// [`parse_irept`]. Operator nodes are rendered infix; any operator operand
+/// is wrapped in `{ }` so precedence round-trips regardless of nesting.
+pub fn print_irept(node: &Irept, interner: &StringInterner) -> String {
+    let mut out = String::new();
+    write_expr(node, interner, &mut out);
+    out
+}
+
+fn write_expr(node: &Irept, interner: &StringInterner, out: &mut String) {
+    if is_operator_node(node, interner) {
+        let op = interner.resolve(node.id).unwrap_or("<NOT FOUND>").to_string();
+        write_operand(&node.subt[0], interner, out);
+        out.push(' ');
+        out.push_str(&op);
+        out.push(' ');
+        write_operand(&node.subt[1], interner, out);
+        return;
+    }
+    write_sexpr_or_leaf(node, interner, out);
+}
+
+fn write_operand(node: &Irept, interner: &StringInterner, out: &mut String) {
+    if is_operator_node(node, interner) {
+        out.push('{');
+        write_expr(node, interner, out);
+        out.push('}');
+    } else {
+        write_expr(node, interner, out);
+    }
+}
+
+fn write_sexpr_or_leaf(node: &Irept, interner: &StringInterner, out: &mut String) {
+    let id = resolve_token(node.id, interner);
+
+    if node.subt.is_empty() && node.named_subt.is_empty() && node.comments.is_empty() {
+        out.push_str(&id);
+        return;
+    }
+
+    out.push('(');
+    out.push_str(&id);
+
+    for child in &node.subt {
+        out.push(' ');
+        write_expr(child, interner, out);
+    }
+
+    let mut named: Vec<_> = node.named_subt.iter().collect();
+    named.sort_by_key(|(key, _)| **key);
+    for (key, child) in named {
+        out.push_str(" :");
+        out.push_str(&resolve_token(*key, interner));
+        out.push(' ');
+        write_expr(child, interner, out);
+    }
+
+    let mut comments: Vec<_> = node.comments.iter().collect();
+    comments.sort_by_key(|(key, _)| **key);
+    for (key, child) in comments {
+        out.push_str(" #");
+        out.push_str(&resolve_token(*key, interner));
+        out.push(' ');
+        write_expr(child, interner, out);
+    }
+
+    out.push(')');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bare_identifier() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("foo", &mut interner).unwrap();
+        assert_eq!(interner.resolve(node.id), Some("foo"));
+        assert!(node.subt.is_empty());
+    }
+
+    #[test]
+    fn test_parse_number_leaf() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("42", &mut interner).unwrap();
+        assert_eq!(interner.resolve(node.id), Some("42"));
+    }
+
+    #[test]
+    fn test_parse_sexpr_with_subt() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("(plus a b)", &mut interner).unwrap();
+        assert_eq!(interner.resolve(node.id), Some("plus"));
+        assert_eq!(node.subt.len(), 2);
+        assert_eq!(interner.resolve(node.subt[0].id), Some("a"));
+        assert_eq!(interner.resolve(node.subt[1].id), Some("b"));
+    }
+
+    #[test]
+    fn test_parse_sexpr_with_named_and_comment() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("(symbol :type int #doc note)", &mut interner).unwrap();
+        let type_key = interner.get_or_intern("type");
+        let doc_key = interner.get_or_intern("doc");
+
+        assert_eq!(interner.resolve(node.named_subt[&type_key].id), Some("int"));
+        assert_eq!(interner.resolve(node.comments[&doc_key].id), Some("note"));
+    }
+
+    #[test]
+    fn test_precedence_climbing_matches_c_precedence() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("a + b * c <= d && e", &mut interner).unwrap();
+
+        // Top-level node should be `&&`.
+        assert_eq!(interner.resolve(node.id), Some("&&"));
+        let lhs = &node.subt[0];
+        assert_eq!(interner.resolve(lhs.id), Some("<="));
+        let lhs_lhs = &lhs.subt[0];
+        assert_eq!(interner.resolve(lhs_lhs.id), Some("+"));
+        let mul = &lhs_lhs.subt[1];
+        assert_eq!(interner.resolve(mul.id), Some("*"));
+    }
+
+    #[test]
+    fn test_left_associativity() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("a - b - c", &mut interner).unwrap();
+
+        assert_eq!(interner.resolve(node.id), Some("-"));
+        let lhs = &node.subt[0];
+        assert_eq!(interner.resolve(lhs.id), Some("-"));
+        assert_eq!(interner.resolve(lhs.subt[0].id), Some("a"));
+        assert_eq!(interner.resolve(lhs.subt[1].id), Some("b"));
+        assert_eq!(interner.resolve(node.subt[1].id), Some("c"));
+    }
+
+    #[test]
+    fn test_right_associativity() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("a ^ b ^ c", &mut interner).unwrap();
+
+        // `^` is right-associative, so this should fold as `a ^ (b ^ c)`.
+        assert_eq!(interner.resolve(node.id), Some("^"));
+        assert_eq!(interner.resolve(node.subt[0].id), Some("a"));
+        let rhs = &node.subt[1];
+        assert_eq!(interner.resolve(rhs.id), Some("^"));
+        assert_eq!(interner.resolve(rhs.subt[0].id), Some("b"));
+        assert_eq!(interner.resolve(rhs.subt[1].id), Some("c"));
+    }
+
+    #[test]
+    fn test_brace_grouping_overrides_precedence() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("{a + b} * c", &mut interner).unwrap();
+
+        assert_eq!(interner.resolve(node.id), Some("*"));
+        assert_eq!(interner.resolve(node.subt[0].id), Some("+"));
+    }
+
+    #[test]
+    fn test_quoted_identifier_round_trips() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("\"weird id\"", &mut interner).unwrap();
+        assert_eq!(interner.resolve(node.id), Some("weird id"));
+    }
+
+    #[test]
+    fn test_parse_error_has_span() {
+        let mut interner = StringInterner::new();
+        let err = parse_irept("(foo", &mut interner).unwrap_err();
+        assert!(err.span.0 >= 4);
+    }
+
+    #[test]
+    fn test_parse_error_on_invalid_character() {
+        let mut interner = StringInterner::new();
+        let err = parse_irept("a @ b", &mut interner).unwrap_err();
+        assert_eq!(err.span, (2, 3));
+    }
+
+    #[test]
+    fn test_print_leaf_round_trips() {
+        let mut interner = StringInterner::new();
+        let node = Irept::new("foo", &mut interner);
+        let text = print_irept(&node, &interner);
+        assert_eq!(text, "foo");
+
+        let reparsed = parse_irept(&text, &mut interner).unwrap();
+        assert_eq!(reparsed, node);
+    }
+
+    #[test]
+    fn test_print_sexpr_round_trips() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("(plus a b :kind sum)", &mut interner).unwrap();
+        let text = print_irept(&node, &interner);
+
+        let reparsed = parse_irept(&text, &mut interner).unwrap();
+        assert_eq!(reparsed, node);
+    }
+
+    #[test]
+    fn test_print_operator_expression_round_trips() {
+        let mut interner = StringInterner::new();
+        let node = parse_irept("a + b * c <= d && e", &mut interner).unwrap();
+        let text = print_irept(&node, &interner);
+
+        let reparsed = parse_irept(&text, &mut interner).unwrap();
+        assert_eq!(reparsed, node);
+    }
+
+    #[test]
+    fn test_print_quotes_non_bare_ids() {
+        let mut interner = StringInterner::new();
+        let node = Irept::new("has space", &mut interner);
+        let text = print_irept(&node, &interner);
+        assert_eq!(text, "\"has space\"");
+    }
+}
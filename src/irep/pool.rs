@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::Irept;
+
+/// Hash-consing pool that deduplicates structurally identical `Irept`
+/// subtrees.
+///
+/// GOTO programs contain enormous numbers of identical type and
+/// subexpression nodes; interning them turns the tree into a DAG and
+/// dramatically cuts memory use. Children must be interned before their
+/// parent (see [`IrepPool::build`]) to guarantee maximal sharing.
+pub struct IrepPool {
+    nodes: HashSet<Rc<Irept>>,
+}
+
+impl IrepPool {
+    pub fn new() -> Self {
+        Self {
+            nodes: HashSet::new(),
+        }
+    }
+
+    /// Returns the pooled `Rc` for a structurally equal node if one already
+    /// exists, otherwise inserts `node` and returns a fresh `Rc` for it.
+    pub fn intern(&mut self, node: Irept) -> Rc<Irept> {
+        let rc = Rc::new(node);
+        if let Some(existing) = self.nodes.get(&rc) {
+            return existing.clone();
+        }
+        self.nodes.insert(rc.clone());
+        rc
+    }
+
+    /// Starts building a node whose children are supplied as already-interned
+    /// `Rc`s; calling [`IrepBuilder::finish`] interns the resulting node.
+    pub fn build(&mut self, id: usize) -> IrepBuilder<'_> {
+        IrepBuilder {
+            pool: self,
+            node: Irept {
+                id,
+                subt: Vec::new(),
+                named_subt: HashMap::new(),
+                comments: HashMap::new(),
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Reports how effective deduplication has been so far.
+    pub fn stats(&self) -> IrepPoolStats {
+        let unique = self.nodes.len();
+        let total_refs: usize = self.nodes.iter().map(Rc::strong_count).sum();
+        IrepPoolStats { unique, total_refs }
+    }
+}
+
+impl Default for IrepPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds an `Irept` node bottom-up from already-interned children, then
+/// interns the finished node in the owning pool.
+pub struct IrepBuilder<'a> {
+    pool: &'a mut IrepPool,
+    node: Irept,
+}
+
+impl<'a> IrepBuilder<'a> {
+    pub fn subt(mut self, child: Rc<Irept>) -> Self {
+        self.node.subt.push(child);
+        self
+    }
+
+    pub fn named(mut self, key: usize, child: Rc<Irept>) -> Self {
+        self.node.named_subt.insert(key, child);
+        self
+    }
+
+    pub fn comment(mut self, key: usize, child: Rc<Irept>) -> Self {
+        self.node.comments.insert(key, child);
+        self
+    }
+
+    pub fn finish(self) -> Rc<Irept> {
+        self.pool.intern(self.node)
+    }
+}
+
+/// Snapshot of how much sharing an `IrepPool` has achieved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IrepPoolStats {
+    pub unique: usize,
+    pub total_refs: usize,
+}
+
+impl IrepPoolStats {
+    /// Average number of strong references per unique node, counting the
+    /// reference the pool itself holds; higher values mean more sharing. An
+    /// empty pool reports `1.0`.
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.unique == 0 {
+            return 1.0;
+        }
+        self.total_refs as f64 / self.unique as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::irep::StringInterner;
+
+    #[test]
+    fn test_new_pool_is_empty() {
+        let pool = IrepPool::new();
+        assert_eq!(pool.len(), 0);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_intern_distinct_nodes() {
+        let mut interner = StringInterner::new();
+        let mut pool = IrepPool::new();
+
+        let a = Irept::new("a", &mut interner);
+        let b = Irept::new("b", &mut interner);
+
+        pool.intern(a);
+        pool.intern(b);
+
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_intern_deduplicates_equal_nodes() {
+        let mut interner = StringInterner::new();
+        let mut pool = IrepPool::new();
+
+        let a1 = Irept::new("a", &mut interner);
+        let a2 = Irept::new("a", &mut interner);
+
+        let rc1 = pool.intern(a1);
+        let rc2 = pool.intern(a2);
+
+        assert_eq!(pool.len(), 1);
+        assert!(Rc::ptr_eq(&rc1, &rc2));
+    }
+
+    #[test]
+    fn test_intern_bumps_refcount() {
+        let mut interner = StringInterner::new();
+        let mut pool = IrepPool::new();
+
+        let a1 = Irept::new("a", &mut interner);
+        let a2 = Irept::new("a", &mut interner);
+
+        let rc1 = pool.intern(a1);
+        assert_eq!(Rc::strong_count(&rc1), 2); // one in pool, one returned
+
+        // Interning a structurally-equal node again returns the same `Rc`
+        // without adding another entry to the pool, so the only new
+        // reference is the one returned (pool, rc1, rc2).
+        let rc2 = pool.intern(a2);
+        assert_eq!(Rc::strong_count(&rc2), 3);
+        assert!(Rc::ptr_eq(&rc1, &rc2));
+    }
+
+    #[test]
+    fn test_builder_shares_common_children() {
+        let mut interner = StringInterner::new();
+        let mut pool = IrepPool::new();
+
+        let leaf_id = interner.get_or_intern("leaf");
+        let leaf1 = pool.build(leaf_id).finish();
+        let leaf2 = pool.build(leaf_id).finish();
+        assert!(Rc::ptr_eq(&leaf1, &leaf2));
+
+        let parent_id = interner.get_or_intern("parent");
+        let field = interner.get_or_intern("field");
+        let parent1 = pool
+            .build(parent_id)
+            .subt(leaf1.clone())
+            .named(field, leaf2.clone())
+            .finish();
+        let parent2 = pool
+            .build(parent_id)
+            .subt(leaf1)
+            .named(field, leaf2)
+            .finish();
+
+        assert!(Rc::ptr_eq(&parent1, &parent2));
+        // one unique leaf, one unique parent
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_stats_on_empty_pool() {
+        let pool = IrepPool::new();
+        let stats = pool.stats();
+        assert_eq!(stats.unique, 0);
+        assert_eq!(stats.total_refs, 0);
+        assert_eq!(stats.dedup_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_stats_reflect_sharing() {
+        let mut interner = StringInterner::new();
+        let mut pool = IrepPool::new();
+
+        let id = interner.get_or_intern("shared");
+        let rc1 = pool.intern(Irept::new("shared", &mut interner));
+        let rc2 = pool.intern(Irept::new("shared", &mut interner));
+        let _ = id;
+
+        let stats = pool.stats();
+        assert_eq!(stats.unique, 1);
+        assert_eq!(stats.total_refs, Rc::strong_count(&rc1));
+        assert!(stats.dedup_ratio() >= 2.0);
+        drop(rc2);
+    }
+}